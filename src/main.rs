@@ -1,10 +1,26 @@
 use failure::{format_err, Error};
 use graphql_client::{GraphQLQuery, Response};
 use serde_json;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+/// A user-supplied mapping from a custom GraphQL scalar name (e.g. `DateTime`)
+/// to the JDDF type it should be validated as.
+type ScalarMapping = HashMap<String, jddf::Type>;
+
+/// Runtime options parsed from CLI flags, plus the lookup
+/// `into_discriminator_entry` needs to inline a union/interface member's
+/// `Properties` schema (RFC 8927 requires every schema in a discriminator's
+/// `mapping` to itself be of the properties form, not a `ref`).
+struct Options {
+    scalar_mapping: ScalarMapping,
+    omit_deprecated: bool,
+    object_defs: HashMap<String, GraphQLType>,
+}
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
+    let mut options = parse_options(std::env::args().skip(1))?;
+
     let graphql_schema: Response<introspection_query::ResponseData> =
         serde_json::from_reader(std::io::stdin())?;
 
@@ -14,10 +30,27 @@ async fn main() -> Result<(), Error> {
         .schema
         .ok_or(format_err!("no schema in graphql response"))?;
 
-    let root_name = graphql_schema.query_type.name.clone();
+    let query_root = graphql_schema.query_type.name.clone();
+    let mutation_root = graphql_schema
+        .mutation_type
+        .as_ref()
+        .and_then(|t| t.name.clone());
+    let subscription_root = graphql_schema
+        .subscription_type
+        .as_ref()
+        .and_then(|t| t.name.clone());
+
+    let gql_types = GraphQLType::from_schema(graphql_schema)?;
+    options.object_defs = gql_types
+        .iter()
+        .filter_map(|t| match t {
+            GraphQLType::Object { name, .. } => Some((name.clone(), t.clone())),
+            _ => None,
+        })
+        .collect();
 
     let mut defs = HashMap::new();
-    for gql_schema in GraphQLType::from_schema(graphql_schema) {
+    for gql_schema in gql_types {
         let name = match &gql_schema {
             GraphQLType::Object { ref name, .. } => name.clone(),
             GraphQLType::Interface { ref name, .. } => name.clone(),
@@ -31,12 +64,36 @@ async fn main() -> Result<(), Error> {
             }
         };
 
-        defs.insert(name, gql_schema.into_jddf());
+        defs.insert(name, gql_schema.into_jddf(&options));
+    }
+
+    let query_root = query_root.unwrap();
+    if options.omit_deprecated {
+        let mut roots = vec![query_root.clone()];
+        roots.extend(mutation_root.clone());
+        roots.extend(subscription_root.clone());
+        defs = reachable_defs(defs, &roots);
+    }
+
+    let mut required = HashMap::new();
+    required.insert("query".to_string(), root_ref(query_root));
+
+    let mut optional = HashMap::new();
+    if let Some(mutation_root) = mutation_root {
+        optional.insert("mutation".to_string(), root_ref(mutation_root));
+    }
+    if let Some(subscription_root) = subscription_root {
+        optional.insert("subscription".to_string(), root_ref(subscription_root));
     }
 
     let schema = jddf::Schema::from_parts(
         Some(defs),
-        Box::new(jddf::Form::Ref(root_name.unwrap())),
+        Box::new(jddf::Form::Properties {
+            required,
+            optional,
+            allow_additional: false,
+            has_required: true,
+        }),
         HashMap::new(),
     );
 
@@ -44,6 +101,107 @@ async fn main() -> Result<(), Error> {
     Ok(())
 }
 
+/// Parses CLI flags into [`Options`]: `--scalar NAME=TYPE` entries (e.g.
+/// `--scalar DateTime=timestamp`) into a [`ScalarMapping`], and the
+/// `--omit-deprecated` flag. Recognized JDDF type names are the lowercase
+/// variant names of `jddf::Type` (`timestamp`, `string`, `int32`, `float64`,
+/// etc).
+fn parse_options(args: impl Iterator<Item = String>) -> Result<Options, Error> {
+    let mut scalar_mapping = HashMap::new();
+    let mut omit_deprecated = false;
+
+    let mut args = args.peekable();
+    while let Some(arg) = args.next() {
+        if arg == "--omit-deprecated" {
+            omit_deprecated = true;
+            continue;
+        }
+
+        let assignment = if arg == "--scalar" {
+            args.next()
+                .ok_or(format_err!("--scalar requires a NAME=TYPE argument"))?
+        } else if let Some(assignment) = arg.strip_prefix("--scalar=") {
+            assignment.to_string()
+        } else {
+            continue;
+        };
+
+        let (name, jddf_type) = assignment
+            .split_once('=')
+            .ok_or(format_err!("expected NAME=TYPE, got {:?}", assignment))?;
+
+        let jddf_type = match jddf_type {
+            "boolean" => jddf::Type::Boolean,
+            "string" => jddf::Type::String,
+            "timestamp" => jddf::Type::Timestamp,
+            "float32" => jddf::Type::Float32,
+            "float64" => jddf::Type::Float64,
+            "int8" => jddf::Type::Int8,
+            "uint8" => jddf::Type::Uint8,
+            "int16" => jddf::Type::Int16,
+            "uint16" => jddf::Type::Uint16,
+            "int32" => jddf::Type::Int32,
+            "uint32" => jddf::Type::Uint32,
+            other => return Err(format_err!("unknown JDDF type {:?}", other)),
+        };
+
+        scalar_mapping.insert(name.to_string(), jddf_type);
+    }
+
+    Ok(Options {
+        scalar_mapping,
+        omit_deprecated,
+        object_defs: HashMap::new(),
+    })
+}
+
+/// Walks `defs` starting from `roots`, following every `Form::Ref` it finds,
+/// and drops any def that the traversal never reaches. Used after
+/// `--omit-deprecated` removes fields, since a def that was only reachable
+/// through a now-dropped field would otherwise be left as a dangling,
+/// unreferenced entry.
+fn reachable_defs(
+    defs: HashMap<String, jddf::Schema>,
+    roots: &[String],
+) -> HashMap<String, jddf::Schema> {
+    let mut seen = HashSet::new();
+    let mut stack: Vec<String> = roots.to_vec();
+
+    while let Some(name) = stack.pop() {
+        if !seen.insert(name.clone()) {
+            continue;
+        }
+
+        if let Some(schema) = defs.get(&name) {
+            collect_refs(schema.form(), &mut stack);
+        }
+    }
+
+    defs.into_iter().filter(|(name, _)| seen.contains(name)).collect()
+}
+
+/// Collects the names of every `Form::Ref` reachable from `form`, without
+/// descending into further defs (that's `reachable_defs`'s job).
+fn collect_refs(form: &jddf::Form, out: &mut Vec<String>) {
+    match form {
+        jddf::Form::Ref(name) => out.push(name.clone()),
+        jddf::Form::Elements(schema) => collect_refs(schema.form(), out),
+        jddf::Form::Properties {
+            required, optional, ..
+        } => {
+            for schema in required.values().chain(optional.values()) {
+                collect_refs(schema.form(), out);
+            }
+        }
+        jddf::Form::Discriminator(_, mapping) => {
+            for schema in mapping.values() {
+                collect_refs(schema.form(), out);
+            }
+        }
+        _ => {}
+    }
+}
+
 #[derive(GraphQLQuery)]
 #[graphql(
     query_path = "src/graphql/introspection_query.graphql",
@@ -52,36 +210,115 @@ async fn main() -> Result<(), Error> {
 )]
 struct IntrospectionQuery;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 enum GraphQLType {
     Ref(String),
     Scalar(String),
     Object {
         name: String,
-        fields: HashMap<String, GraphQLType>,
+        description: Option<String>,
+        fields: HashMap<String, Field>,
     },
     Interface {
         name: String,
+        description: Option<String>,
         impls: Vec<GraphQLType>,
     },
     Union {
         name: String,
+        description: Option<String>,
         types: Vec<GraphQLType>,
     },
     Enum {
         name: String,
-        values: Vec<String>,
+        description: Option<String>,
+        values: Vec<EnumValue>,
     },
     Input {
         name: String,
-        fields: HashMap<String, GraphQLType>,
+        description: Option<String>,
+        fields: HashMap<String, Field>,
     },
     NonNull(Box<GraphQLType>),
     List(Box<GraphQLType>),
 }
 
+/// An object or input object field, together with the introspection
+/// metadata GraphQL carries about it.
+#[derive(Debug, Clone)]
+struct Field {
+    gql_type: GraphQLType,
+    description: Option<String>,
+    is_deprecated: bool,
+    deprecation_reason: Option<String>,
+}
+
+/// A single member of an enum, together with its introspection metadata.
+#[derive(Debug, Clone)]
+struct EnumValue {
+    name: String,
+    description: Option<String>,
+    is_deprecated: bool,
+    deprecation_reason: Option<String>,
+}
+
+/// Builds a `Form::Ref` schema pointing at a top-level def by name, for use
+/// in the root schema's `query`/`mutation`/`subscription` properties.
+fn root_ref(name: String) -> jddf::Schema {
+    jddf::Schema::from_parts(None, Box::new(jddf::Form::Ref(name)), HashMap::new())
+}
+
+/// Builds a JDDF metadata map carrying a type's or field's `description`.
+fn describe(description: Option<String>) -> HashMap<String, serde_json::Value> {
+    let mut metadata = HashMap::new();
+    if let Some(description) = description {
+        metadata.insert("description".to_string(), serde_json::Value::String(description));
+    }
+    metadata
+}
+
+/// Merges `extra` into a schema's existing metadata. `jddf::Schema` only
+/// exposes its metadata through the read-only `extra()` accessor, so adding
+/// to it after the fact means rebuilding the schema via `from_parts` with the
+/// combined map rather than mutating it in place.
+fn with_extra(schema: jddf::Schema, extra: HashMap<String, serde_json::Value>) -> jddf::Schema {
+    let mut merged = schema.extra().clone();
+    merged.extend(extra);
+    jddf::Schema::from_parts(schema.definitions().clone(), Box::new(schema.form().clone()), merged)
+}
+
+/// Converts a [`Field`] into a `(is_required, schema)` pair, merging its
+/// `description`, `isDeprecated`, and `deprecationReason` into the schema's
+/// metadata. A field is required iff its type is `NonNull`.
+fn field_into_jddf(field: Field, options: &Options) -> (bool, jddf::Schema) {
+    let Field {
+        gql_type,
+        description,
+        is_deprecated,
+        deprecation_reason,
+    } = field;
+
+    let (is_required, schema) = match gql_type {
+        GraphQLType::NonNull(gql_type) => (true, gql_type.into_jddf(options)),
+        gql_type => (false, gql_type.into_jddf(options)),
+    };
+
+    let mut extra = describe(description);
+    if is_deprecated {
+        extra.insert("deprecated".to_string(), serde_json::Value::Bool(true));
+        if let Some(reason) = deprecation_reason {
+            extra.insert(
+                "deprecationReason".to_string(),
+                serde_json::Value::String(reason),
+            );
+        }
+    }
+
+    (is_required, with_extra(schema, extra))
+}
+
 impl GraphQLType {
-    fn into_jddf(self) -> jddf::Schema {
+    fn into_jddf(self, options: &Options) -> jddf::Schema {
         match self {
             Self::Ref(name) => {
                 jddf::Schema::from_parts(None, Box::new(jddf::Form::Ref(name)), HashMap::new())
@@ -108,19 +345,47 @@ impl GraphQLType {
                     Box::new(jddf::Form::Type(jddf::Type::String)),
                     HashMap::new(),
                 ),
-                _ => jddf::Schema::from_parts(None, Box::new(jddf::Form::Empty), HashMap::new()),
+                other => match options.scalar_mapping.get(other) {
+                    Some(jddf_type) => jddf::Schema::from_parts(
+                        None,
+                        Box::new(jddf::Form::Type(jddf_type.clone())),
+                        HashMap::new(),
+                    ),
+                    None => {
+                        eprintln!(
+                            "warning: custom scalar {:?} has no --scalar mapping; \
+                             generated schema will accept any value for it",
+                            other
+                        );
+                        jddf::Schema::from_parts(None, Box::new(jddf::Form::Empty), HashMap::new())
+                    }
+                },
             },
 
-            Self::Object { fields, .. } => {
+            Self::Object {
+                description,
+                fields,
+                ..
+            } => {
                 let mut required = HashMap::new();
                 let mut optional = HashMap::new();
                 for (name, field) in fields {
-                    match field {
-                        Self::NonNull(gql_type) => {
-                            required.insert(name, gql_type.into_jddf());
+                    // The discriminator form supplies "__typename" itself; a
+                    // schema in a discriminator mapping must not redefine it.
+                    if name == "__typename" {
+                        continue;
+                    }
+
+                    if options.omit_deprecated && field.is_deprecated {
+                        continue;
+                    }
+
+                    match field_into_jddf(field, options) {
+                        (true, schema) => {
+                            required.insert(name, schema);
                         }
-                        _ => {
-                            optional.insert(name, field.into_jddf());
+                        (false, schema) => {
+                            optional.insert(name, schema);
                         }
                     }
                 }
@@ -133,14 +398,14 @@ impl GraphQLType {
                         allow_additional: false,
                         has_required: true,
                     }),
-                    HashMap::new(),
+                    describe(description),
                 )
             }
 
             Self::List(gql_type) => match *gql_type {
                 Self::NonNull(gql_type) => jddf::Schema::from_parts(
                     None,
-                    Box::new(jddf::Form::Elements(gql_type.into_jddf())),
+                    Box::new(jddf::Form::Elements(gql_type.into_jddf(options))),
                     HashMap::new(),
                 ),
                 _ => jddf::Schema::from_parts(
@@ -154,32 +419,93 @@ impl GraphQLType {
                 ),
             },
 
-            // TODO: Maybe have a struct with the known-existing fields, instead?
-            Self::Interface { .. } => {
-                jddf::Schema::from_parts(None, Box::new(jddf::Form::Empty), HashMap::new())
-            }
-
-            // TODO: Maybe have a struct with the known-existing fields, instead?
-            Self::Union { .. } => {
-                jddf::Schema::from_parts(None, Box::new(jddf::Form::Empty), HashMap::new())
-            }
+            Self::Interface {
+                description, impls, ..
+            } => jddf::Schema::from_parts(
+                None,
+                Box::new(jddf::Form::Discriminator(
+                    "__typename".into(),
+                    impls
+                        .into_iter()
+                        .map(|t| Self::into_discriminator_entry(t, options))
+                        .collect(),
+                )),
+                describe(description),
+            ),
 
-            Self::Enum { values, .. } => jddf::Schema::from_parts(
+            Self::Union {
+                description, types, ..
+            } => jddf::Schema::from_parts(
                 None,
-                Box::new(jddf::Form::Enum(values.into_iter().collect())),
-                HashMap::new(),
+                Box::new(jddf::Form::Discriminator(
+                    "__typename".into(),
+                    types
+                        .into_iter()
+                        .map(|t| Self::into_discriminator_entry(t, options))
+                        .collect(),
+                )),
+                describe(description),
             ),
 
-            Self::Input { fields, .. } => {
+            Self::Enum {
+                description, values, ..
+            } => {
+                let values: Vec<EnumValue> = if options.omit_deprecated {
+                    values.into_iter().filter(|v| !v.is_deprecated).collect()
+                } else {
+                    values
+                };
+
+                let mut metadata = describe(description);
+
+                let value_metadata: serde_json::Map<String, serde_json::Value> = values
+                    .iter()
+                    .filter(|v| v.description.is_some() || v.is_deprecated)
+                    .map(|v| {
+                        let mut meta = describe(v.description.clone());
+                        if v.is_deprecated {
+                            meta.insert("deprecated".to_string(), serde_json::Value::Bool(true));
+                            if let Some(reason) = &v.deprecation_reason {
+                                meta.insert(
+                                    "deprecationReason".to_string(),
+                                    serde_json::Value::String(reason.clone()),
+                                );
+                            }
+                        }
+                        (v.name.clone(), serde_json::Value::Object(meta.into_iter().collect()))
+                    })
+                    .collect();
+
+                if !value_metadata.is_empty() {
+                    metadata.insert(
+                        "enumValues".to_string(),
+                        serde_json::Value::Object(value_metadata),
+                    );
+                }
+
+                jddf::Schema::from_parts(
+                    None,
+                    Box::new(jddf::Form::Enum(values.into_iter().map(|v| v.name).collect())),
+                    metadata,
+                )
+            }
+
+            Self::Input {
+                description, fields, ..
+            } => {
                 let mut required = HashMap::new();
                 let mut optional = HashMap::new();
                 for (name, field) in fields {
-                    match field {
-                        Self::NonNull(gql_type) => {
-                            required.insert(name, gql_type.into_jddf());
+                    if options.omit_deprecated && field.is_deprecated {
+                        continue;
+                    }
+
+                    match field_into_jddf(field, options) {
+                        (true, schema) => {
+                            required.insert(name, schema);
                         }
-                        _ => {
-                            optional.insert(name, field.into_jddf());
+                        (false, schema) => {
+                            optional.insert(name, schema);
                         }
                     }
                 }
@@ -192,7 +518,7 @@ impl GraphQLType {
                         allow_additional: false,
                         has_required: true,
                     }),
-                    HashMap::new(),
+                    describe(description),
                 )
             }
 
@@ -200,15 +526,31 @@ impl GraphQLType {
         }
     }
 
-    fn from_schema(schema: introspection_query::IntrospectionQuerySchema) -> Vec<GraphQLType> {
+    /// Turns one of a union's `types` or an interface's `impls` into a
+    /// `(type name, schema)` pair for a discriminator mapping. RFC 8927
+    /// requires the mapped schema itself to be of the properties form, so
+    /// this inlines the member's `Properties` schema rather than a `Ref`.
+    fn into_discriminator_entry(gql_type: GraphQLType, options: &Options) -> (String, jddf::Schema) {
+        match gql_type {
+            Self::Ref(name) => {
+                let object = options.object_defs.get(&name).cloned().unwrap();
+                (name, object.into_jddf(options))
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn from_schema(
+        schema: introspection_query::IntrospectionQuerySchema,
+    ) -> Result<Vec<GraphQLType>, Error> {
         schema
             .types
             .into_iter()
-            .map(|t| Self::from_full_type(t.full_type))
+            .map(Self::from_full_type)
             .collect()
     }
 
-    fn from_full_type(full_type: introspection_query::FullType) -> GraphQLType {
+    fn from_full_type(full_type: introspection_query::FullType) -> Result<GraphQLType, Error> {
         use introspection_query::{FullType, __TypeKind as Kind};
 
         match full_type {
@@ -216,228 +558,245 @@ impl GraphQLType {
                 kind: Kind::SCALAR,
                 name: Some(name),
                 ..
-            } => GraphQLType::Scalar(name),
+            } => Ok(GraphQLType::Scalar(name)),
 
             FullType {
                 kind: Kind::OBJECT,
                 name: Some(name),
+                description,
                 fields: Some(fields),
                 ..
-            } => GraphQLType::Object {
+            } => Ok(GraphQLType::Object {
                 name,
+                description,
                 fields: fields
                     .into_iter()
-                    .map(|field| (field.name, Self::from_type_ref(field.type_.type_ref)))
-                    .collect(),
-            },
+                    .map(|field| {
+                        Ok((
+                            field.name,
+                            Field {
+                                gql_type: from_type_ref(field.type_)?,
+                                description: field.description,
+                                is_deprecated: field.is_deprecated,
+                                deprecation_reason: field.deprecation_reason,
+                            },
+                        ))
+                    })
+                    .collect::<Result<_, Error>>()?,
+            }),
 
             FullType {
                 kind: Kind::INTERFACE,
                 name: Some(name),
+                description,
                 possible_types: Some(possible_types),
                 ..
-            } => GraphQLType::Interface {
+            } => Ok(GraphQLType::Interface {
                 name,
+                description,
                 impls: possible_types
                     .into_iter()
-                    .map(|t| Self::from_type_ref(t.type_ref))
-                    .collect(),
-            },
+                    .map(from_type_ref)
+                    .collect::<Result<_, Error>>()?,
+            }),
 
             FullType {
                 kind: Kind::UNION,
                 name: Some(name),
+                description,
                 possible_types: Some(possible_types),
                 ..
-            } => GraphQLType::Union {
+            } => Ok(GraphQLType::Union {
                 name,
+                description,
                 types: possible_types
                     .into_iter()
-                    .map(|t| Self::from_type_ref(t.type_ref))
-                    .collect(),
-            },
+                    .map(from_type_ref)
+                    .collect::<Result<_, Error>>()?,
+            }),
 
             FullType {
                 kind: Kind::ENUM,
                 name: Some(name),
+                description,
                 enum_values: Some(enum_values),
                 ..
-            } => GraphQLType::Enum {
+            } => Ok(GraphQLType::Enum {
                 name,
-                values: enum_values.into_iter().map(|v| v.name).collect(),
-            },
+                description,
+                values: enum_values
+                    .into_iter()
+                    .map(|v| EnumValue {
+                        name: v.name,
+                        description: v.description,
+                        is_deprecated: v.is_deprecated,
+                        deprecation_reason: v.deprecation_reason,
+                    })
+                    .collect(),
+            }),
 
             FullType {
                 kind: Kind::INPUT_OBJECT,
                 name: Some(name),
+                description,
                 input_fields: Some(input_fields),
                 ..
-            } => GraphQLType::Input {
+            } => Ok(GraphQLType::Input {
                 name,
+                description,
                 fields: input_fields
                     .into_iter()
                     .map(|field| {
-                        (
-                            field.input_value.name,
-                            Self::from_type_ref(field.input_value.type_.type_ref),
-                        )
+                        Ok((
+                            field.name,
+                            Field {
+                                gql_type: from_type_ref(field.type_)?,
+                                description: field.description,
+                                is_deprecated: false,
+                                deprecation_reason: None,
+                            },
+                        ))
                     })
-                    .collect(),
-            },
+                    .collect::<Result<_, Error>>()?,
+            }),
 
             _ => unreachable!(),
         }
     }
+}
 
-    fn from_type_ref(type_ref: introspection_query::TypeRef) -> GraphQLType {
-        match type_ref {
-            introspection_query::TypeRef {
-                name: Some(name), ..
-            } => GraphQLType::Ref(name),
-            introspection_query::TypeRef {
-                kind: introspection_query::__TypeKind::NON_NULL,
-                of_type: Some(of_type),
-                ..
-            } => GraphQLType::NonNull(Box::new(Self::from_type_ref2(of_type))),
-            introspection_query::TypeRef {
-                kind: introspection_query::__TypeKind::LIST,
-                of_type: Some(of_type),
-                ..
-            } => GraphQLType::List(Box::new(Self::from_type_ref2(of_type))),
-            _ => unreachable!(),
-        }
-    }
+/// A single layer of GraphQL's `NON_NULL`/`LIST` type wrapping, as chased
+/// through the `ofType` chain by [`flatten_type_ref`].
+#[derive(Debug)]
+enum Wrapper {
+    NonNull,
+    List,
+}
 
-    fn from_type_ref2(type_ref: introspection_query::TypeRefOfType) -> GraphQLType {
-        match type_ref {
-            introspection_query::TypeRefOfType {
-                name: Some(name), ..
-            } => GraphQLType::Ref(name),
-            introspection_query::TypeRefOfType {
-                kind: introspection_query::__TypeKind::NON_NULL,
-                of_type: Some(of_type),
-                ..
-            } => GraphQLType::NonNull(Box::new(Self::from_type_ref3(of_type))),
-            introspection_query::TypeRefOfType {
-                kind: introspection_query::__TypeKind::LIST,
-                of_type: Some(of_type),
-                ..
-            } => GraphQLType::List(Box::new(Self::from_type_ref3(of_type))),
-            _ => unreachable!(),
-        }
-    }
+/// A common view over the `TypeRef`, `TypeRefOfType`, `TypeRefOfTypeOfType`,
+/// ... structs that `graphql_client` generates for each level of the
+/// `ofType` chain in `introspection_query.graphql`. Every level has the same
+/// three pieces of information (`kind`, `name`, and the next-level `ofType`,
+/// if any); `into_parts` exposes them uniformly so [`flatten_type_ref`] can
+/// walk the whole chain with one recursive function instead of one function
+/// per level.
+trait TypeRefLike: Sized {
+    type Next: TypeRefLike;
+
+    fn into_parts(
+        self,
+    ) -> (
+        introspection_query::__TypeKind,
+        Option<String>,
+        Option<Self::Next>,
+    );
+}
 
-    fn from_type_ref3(type_ref: introspection_query::TypeRefOfTypeOfType) -> GraphQLType {
-        match type_ref {
-            introspection_query::TypeRefOfTypeOfType {
-                name: Some(name), ..
-            } => GraphQLType::Ref(name),
-            introspection_query::TypeRefOfTypeOfType {
-                kind: introspection_query::__TypeKind::NON_NULL,
-                of_type: Some(of_type),
-                ..
-            } => GraphQLType::NonNull(Box::new(Self::from_type_ref4(of_type))),
-            introspection_query::TypeRefOfTypeOfType {
-                kind: introspection_query::__TypeKind::LIST,
-                of_type: Some(of_type),
-                ..
-            } => GraphQLType::List(Box::new(Self::from_type_ref4(of_type))),
-            _ => unreachable!(),
-        }
-    }
+/// Implements [`TypeRefLike`] for a `graphql_client`-generated type-ref
+/// struct whose `of_type` wraps the next level down.
+macro_rules! impl_type_ref_like {
+    ($ty:ident, $next:ident) => {
+        impl TypeRefLike for introspection_query::$ty {
+            type Next = introspection_query::$next;
 
-    fn from_type_ref4(type_ref: introspection_query::TypeRefOfTypeOfTypeOfType) -> GraphQLType {
-        match type_ref {
-            introspection_query::TypeRefOfTypeOfTypeOfType {
-                name: Some(name), ..
-            } => GraphQLType::Ref(name),
-            introspection_query::TypeRefOfTypeOfTypeOfType {
-                kind: introspection_query::__TypeKind::NON_NULL,
-                of_type: Some(of_type),
-                ..
-            } => GraphQLType::NonNull(Box::new(Self::from_type_ref5(of_type))),
-            introspection_query::TypeRefOfTypeOfTypeOfType {
-                kind: introspection_query::__TypeKind::LIST,
-                of_type: Some(of_type),
-                ..
-            } => GraphQLType::List(Box::new(Self::from_type_ref5(of_type))),
-            _ => unreachable!(),
+            fn into_parts(self) -> (introspection_query::__TypeKind, Option<String>, Option<Self::Next>) {
+                (self.kind, self.name, self.of_type)
+            }
         }
-    }
+    };
+}
 
-    fn from_type_ref5(
-        type_ref: introspection_query::TypeRefOfTypeOfTypeOfTypeOfType,
-    ) -> GraphQLType {
-        match type_ref {
-            introspection_query::TypeRefOfTypeOfTypeOfTypeOfType {
-                name: Some(name), ..
-            } => GraphQLType::Ref(name),
-            introspection_query::TypeRefOfTypeOfTypeOfTypeOfType {
-                kind: introspection_query::__TypeKind::NON_NULL,
-                of_type: Some(of_type),
-                ..
-            } => GraphQLType::NonNull(Box::new(Self::from_type_ref6(of_type))),
-            introspection_query::TypeRefOfTypeOfTypeOfTypeOfType {
-                kind: introspection_query::__TypeKind::LIST,
-                of_type: Some(of_type),
-                ..
-            } => GraphQLType::List(Box::new(Self::from_type_ref6(of_type))),
-            _ => unreachable!(),
-        }
-    }
+/// Implements [`TypeRefLike`] for the deepest type-ref struct the query
+/// fetches, which has no further `ofType` field of its own.
+macro_rules! impl_type_ref_like_terminal {
+    ($ty:ident) => {
+        impl TypeRefLike for introspection_query::$ty {
+            type Next = introspection_query::$ty;
 
-    fn from_type_ref6(
-        type_ref: introspection_query::TypeRefOfTypeOfTypeOfTypeOfTypeOfType,
-    ) -> GraphQLType {
-        match type_ref {
-            introspection_query::TypeRefOfTypeOfTypeOfTypeOfTypeOfType {
-                name: Some(name), ..
-            } => GraphQLType::Ref(name),
-            introspection_query::TypeRefOfTypeOfTypeOfTypeOfTypeOfType {
-                kind: introspection_query::__TypeKind::NON_NULL,
-                of_type: Some(of_type),
-                ..
-            } => GraphQLType::NonNull(Box::new(Self::from_type_ref7(of_type))),
-            introspection_query::TypeRefOfTypeOfTypeOfTypeOfTypeOfType {
-                kind: introspection_query::__TypeKind::LIST,
-                of_type: Some(of_type),
-                ..
-            } => GraphQLType::List(Box::new(Self::from_type_ref7(of_type))),
-            _ => unreachable!(),
+            fn into_parts(self) -> (introspection_query::__TypeKind, Option<String>, Option<Self::Next>) {
+                (self.kind, self.name, None)
+            }
         }
-    }
+    };
+}
 
-    fn from_type_ref7(
-        type_ref: introspection_query::TypeRefOfTypeOfTypeOfTypeOfTypeOfTypeOfType,
-    ) -> GraphQLType {
-        match type_ref {
-            introspection_query::TypeRefOfTypeOfTypeOfTypeOfTypeOfTypeOfType {
-                name: Some(name),
-                ..
-            } => GraphQLType::Ref(name),
-            introspection_query::TypeRefOfTypeOfTypeOfTypeOfTypeOfTypeOfType {
-                kind: introspection_query::__TypeKind::NON_NULL,
-                of_type: Some(of_type),
-                ..
-            } => GraphQLType::NonNull(Box::new(Self::from_type_ref8(of_type))),
-            introspection_query::TypeRefOfTypeOfTypeOfTypeOfTypeOfTypeOfType {
-                kind: introspection_query::__TypeKind::LIST,
-                of_type: Some(of_type),
-                ..
-            } => GraphQLType::List(Box::new(Self::from_type_ref8(of_type))),
-            _ => unreachable!(),
-        }
+impl_type_ref_like!(TypeRef, TypeRefOfType);
+impl_type_ref_like!(TypeRefOfType, TypeRefOfTypeOfType);
+impl_type_ref_like!(TypeRefOfTypeOfType, TypeRefOfTypeOfTypeOfType);
+impl_type_ref_like!(TypeRefOfTypeOfTypeOfType, TypeRefOfTypeOfTypeOfTypeOfType);
+impl_type_ref_like!(
+    TypeRefOfTypeOfTypeOfTypeOfType,
+    TypeRefOfTypeOfTypeOfTypeOfTypeOfType
+);
+impl_type_ref_like!(
+    TypeRefOfTypeOfTypeOfTypeOfTypeOfType,
+    TypeRefOfTypeOfTypeOfTypeOfTypeOfTypeOfType
+);
+impl_type_ref_like!(
+    TypeRefOfTypeOfTypeOfTypeOfTypeOfTypeOfType,
+    TypeRefOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfType
+);
+impl_type_ref_like!(
+    TypeRefOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfType,
+    TypeRefOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfType
+);
+impl_type_ref_like!(
+    TypeRefOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfType,
+    TypeRefOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfType
+);
+impl_type_ref_like!(
+    TypeRefOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfType,
+    TypeRefOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfType
+);
+impl_type_ref_like!(
+    TypeRefOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfType,
+    TypeRefOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfType
+);
+impl_type_ref_like_terminal!(
+    TypeRefOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfType
+);
+
+/// Walks a `graphql_client`-generated type-ref chain (`TypeRef`,
+/// `TypeRefOfType`, ...) via [`TypeRefLike`], folding every `NON_NULL`/`LIST`
+/// layer it passes through into `wrappers`, innermost-last. Returns the name
+/// of the named type at the bottom of the chain.
+fn flatten_type_ref<T: TypeRefLike>(
+    type_ref: T,
+    wrappers: &mut Vec<Wrapper>,
+) -> Result<String, Error> {
+    let (kind, name, of_type) = type_ref.into_parts();
+
+    if let Some(name) = name {
+        return Ok(name);
     }
 
-    fn from_type_ref8(
-        type_ref: introspection_query::TypeRefOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfType,
-    ) -> GraphQLType {
-        match type_ref {
-            introspection_query::TypeRefOfTypeOfTypeOfTypeOfTypeOfTypeOfTypeOfType {
-                name: Some(name),
-                ..
-            } => GraphQLType::Ref(name),
-            _ => unreachable!(),
-        }
+    wrappers.push(match kind {
+        introspection_query::__TypeKind::NON_NULL => Wrapper::NonNull,
+        introspection_query::__TypeKind::LIST => Wrapper::List,
+        other => return Err(format_err!("unexpected unnamed type ref of kind {:?}", other)),
+    });
+
+    match of_type {
+        Some(of_type) => flatten_type_ref(of_type, wrappers),
+        None => Err(format_err!(
+            "type reference is nested deeper than the introspection query supports"
+        )),
     }
 }
+
+/// Converts a `graphql_client` type ref into a [`GraphQLType`] by flattening
+/// its `ofType` chain with [`flatten_type_ref`] and folding the resulting
+/// `NON_NULL`/`LIST` wrappers back around the named type, from the inside
+/// out.
+fn from_type_ref(type_ref: introspection_query::TypeRef) -> Result<GraphQLType, Error> {
+    let mut wrappers = Vec::new();
+    let name = flatten_type_ref(type_ref, &mut wrappers)?;
+
+    Ok(wrappers
+        .into_iter()
+        .rev()
+        .fold(GraphQLType::Ref(name), |gql_type, wrapper| match wrapper {
+            Wrapper::NonNull => GraphQLType::NonNull(Box::new(gql_type)),
+            Wrapper::List => GraphQLType::List(Box::new(gql_type)),
+        }))
+}